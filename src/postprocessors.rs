@@ -1,10 +1,19 @@
 //! A collection of officially maintained [postprocessors][crate::Postprocessor].
 
 use super::{Context, MarkdownEvents, PostprocessorResult};
-use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Options, Parser, Tag};
 use regex::Regex;
 use serde_yaml::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::rc::Rc;
 use std::string::String;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
 
 /// This postprocessor converts all soft line breaks to hard line breaks. Enabling this mimics
 /// Obsidian's _'Strict line breaks'_ setting.
@@ -140,6 +149,893 @@ pub fn remove_obsidian_comments(
     PostprocessorResult::Continue
 }
 
+/// This postprocessor wraps bare URLs (`https://example.com`) in proper links, so they're
+/// rendered as clickable links by targets which don't autolink plain text themselves.
+pub fn autolink_bare_urls(
+    _context: &mut Context,
+    events: &mut MarkdownEvents,
+) -> PostprocessorResult {
+    let re = Regex::new(r"(?i)\bhttps?://[^\s<>\]]+").unwrap();
+    let mut output = Vec::with_capacity(events.len());
+    let mut inside_codeblock = false;
+    let mut inside_link = false;
+
+    for event in &mut *events {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                inside_codeblock = true;
+                output.push(event.to_owned());
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                inside_codeblock = false;
+                output.push(event.to_owned());
+            }
+            Event::Start(Tag::Link(..)) => {
+                inside_link = true;
+                output.push(event.to_owned());
+            }
+            Event::End(Tag::Link(..)) => {
+                inside_link = false;
+                output.push(event.to_owned());
+            }
+            Event::Text(ref text) if !inside_codeblock && !inside_link => {
+                match autolink_text(text, &re) {
+                    Some(replacement) => output.extend(replacement),
+                    None => output.push(event.to_owned()),
+                }
+            }
+            _ => {
+                output.push(event.to_owned());
+            }
+        }
+    }
+
+    *events = output;
+    PostprocessorResult::Continue
+}
+
+/// Finds bare URLs in `text` and returns the events they should be replaced with, or `None` if
+/// `text` contains no URL.
+fn autolink_text(text: &str, re: &Regex) -> Option<Vec<Event<'static>>> {
+    let mut last_match_end = 0;
+    let mut found_match = false;
+    let mut output = Vec::new();
+
+    for m in re.find_iter(text) {
+        found_match = true;
+
+        // Trailing `.`/`,` are punctuation, not part of the URL. A trailing `)` is only
+        // punctuation if it's unbalanced within the match (e.g. the closing paren of `(see
+        // https://example.com)`); a balanced one is kept, so URLs with legitimate parens (e.g.
+        // `.../wiki/Foo_(bar)`) survive intact.
+        let mut url = m.as_str();
+        while let Some(last) = url.chars().last() {
+            let trim = match last {
+                '.' | ',' => true,
+                ')' => url.matches('(').count() < url.matches(')').count(),
+                _ => false,
+            };
+            if !trim {
+                break;
+            }
+            url = &url[..url.len() - last.len_utf8()];
+        }
+        let trailer = &m.as_str()[url.len()..];
+
+        output.push(Event::Text(CowStr::from(
+            text[last_match_end..m.start()].to_string(),
+        )));
+        output.push(Event::Start(Tag::Link(
+            LinkType::Autolink,
+            url.to_string().into(),
+            "".into(),
+        )));
+        output.push(Event::Text(CowStr::from(url.to_string())));
+        output.push(Event::End(Tag::Link(
+            LinkType::Autolink,
+            url.to_string().into(),
+            "".into(),
+        )));
+        // Whatever was trimmed off the end of the URL becomes part of the following text.
+        last_match_end = m.start() + url.len();
+    }
+
+    if !found_match {
+        return None;
+    }
+
+    output.push(Event::Text(CowStr::from(
+        text[last_match_end..].to_string(),
+    )));
+    Some(output)
+}
+
+#[test]
+fn test_autolink_text() {
+    let re = Regex::new(r"(?i)\bhttps?://[^\s<>\]]+").unwrap();
+
+    assert_eq!(
+        autolink_text("no urls here", &re),
+        None,
+        "Text without a bare URL is left untouched"
+    );
+
+    let events = autolink_text("See https://example.com/ for details", &re)
+        .expect("a bare URL should be found");
+    assert_eq!(
+        events,
+        vec![
+            Event::Text(CowStr::from("See ")),
+            Event::Start(Tag::Link(
+                LinkType::Autolink,
+                "https://example.com/".into(),
+                "".into()
+            )),
+            Event::Text(CowStr::from("https://example.com/")),
+            Event::End(Tag::Link(
+                LinkType::Autolink,
+                "https://example.com/".into(),
+                "".into()
+            )),
+            Event::Text(CowStr::from(" for details")),
+        ],
+        "A bare URL is wrapped in an autolink, keeping the surrounding text"
+    );
+
+    let events =
+        autolink_text("(see http://example.com).", &re).expect("a bare URL should be found");
+    assert_eq!(
+        events.last(),
+        Some(&Event::Text(CowStr::from(")."))),
+        "A trailing `)` and `.` are split back out of the URL into the following text"
+    );
+
+    let events = autolink_text(
+        "See https://en.wikipedia.org/wiki/Foo_(bar).",
+        &re,
+    )
+    .expect("a bare URL should be found");
+    assert_eq!(
+        events,
+        vec![
+            Event::Text(CowStr::from("See ")),
+            Event::Start(Tag::Link(
+                LinkType::Autolink,
+                "https://en.wikipedia.org/wiki/Foo_(bar)".into(),
+                "".into()
+            )),
+            Event::Text(CowStr::from("https://en.wikipedia.org/wiki/Foo_(bar)")),
+            Event::End(Tag::Link(
+                LinkType::Autolink,
+                "https://en.wikipedia.org/wiki/Foo_(bar)".into(),
+                "".into()
+            )),
+            Event::Text(CowStr::from(".")),
+        ],
+        "A balanced trailing `)` is kept as part of the URL, only the `.` is split out"
+    );
+}
+
+/// This postprocessor pre-renders fenced code blocks into syntax-highlighted HTML using
+/// `syntect`, for targets which don't do their own (client-side) highlighting. It's opt-in: pass
+/// the name of a `syntect` theme (one of the bundled defaults, e.g. `"InspiredGitHub"`) to enable
+/// it.
+pub fn highlight_code_blocks(
+    theme: String,
+) -> impl Fn(&mut Context, &mut MarkdownEvents) -> PostprocessorResult {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let theme_set = ThemeSet::load_defaults();
+
+    move |_context: &mut Context, events: &mut MarkdownEvents| -> PostprocessorResult {
+        let theme = match theme_set.themes.get(&theme) {
+            Some(theme) => theme,
+            None => return PostprocessorResult::Continue,
+        };
+
+        let mut output = Vec::with_capacity(events.len());
+        let mut current_block: Option<(String, String)> = None;
+
+        for event in events.drain(..) {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang))) => {
+                    current_block = Some((lang.to_string(), String::new()));
+                }
+                Event::Text(ref text) if current_block.is_some() => {
+                    current_block.as_mut().unwrap().1.push_str(text);
+                }
+                Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(_))) if current_block.is_some() => {
+                    let (lang, code) = current_block.take().unwrap();
+                    output.push(Event::Html(CowStr::from(render_highlighted_code_block(
+                        &lang,
+                        &code,
+                        &syntax_set,
+                        theme,
+                    ))));
+                }
+                other => output.push(other),
+            }
+        }
+
+        *events = output;
+        PostprocessorResult::Continue
+    }
+}
+
+/// Renders a single fenced code block's contents into `<pre><code class="language-...">` with
+/// `syntect` syntax highlighting inlined as HTML, falling back to plain text when `lang` isn't
+/// recognized.
+fn render_highlighted_code_block(
+    lang: &str,
+    code: &str,
+    syntax_set: &SyntaxSet,
+    theme: &syntect::highlighting::Theme,
+) -> String {
+    let syntax = syntax_set
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut html = format!("<pre><code class=\"language-{}\">", lang);
+    for line in LinesWithEndings::from(code) {
+        if let Ok(ranges) = highlighter.highlight_line(line, syntax_set) {
+            if let Ok(rendered) = styled_line_to_highlighted_html(&ranges, IncludeBackground::No) {
+                let _ = write!(html, "{}", rendered);
+            }
+        }
+    }
+    html.push_str("</code></pre>");
+    html
+}
+
+#[test]
+fn test_render_highlighted_code_block() {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let theme_set = ThemeSet::load_defaults();
+    let theme = &theme_set.themes["InspiredGitHub"];
+
+    let html = render_highlighted_code_block("rust", "fn main() {}\n", &syntax_set, theme);
+    assert!(
+        html.starts_with("<pre><code class=\"language-rust\">"),
+        "The block is wrapped with a language class carrying the requested language: {}",
+        html
+    );
+    assert!(
+        html.ends_with("</code></pre>"),
+        "The block is closed with matching tags: {}",
+        html
+    );
+    assert!(
+        html.contains("fn") && html.contains("main"),
+        "The original source text is still present in the rendered HTML: {}",
+        html
+    );
+
+    let html =
+        render_highlighted_code_block("not-a-real-language", "plain text\n", &syntax_set, theme);
+    assert!(
+        html.contains("plain text"),
+        "An unrecognized language falls back to plain text instead of panicking: {}",
+        html
+    );
+}
+
+/// Tags which never require a matching closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// The default set of tags left untouched by [`sanitize_html`]'s `Strip` mode.
+const DEFAULT_ALLOWED_TAGS: &[&str] = &["b", "i", "em", "strong", "code", "br"];
+
+/// A single diagnostic produced by [`sanitize_html`]'s `Report` mode: the offending tag, plus the
+/// raw HTML it was found in, so a caller (or a test) can see exactly what triggered it. For a tag
+/// still open when the note ends, `context` is a fixed message saying so instead; the HTML the tag
+/// was opened in is not kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlDiagnostic {
+    pub tag: String,
+    pub context: String,
+}
+
+/// Controls how [`sanitize_html`] treats raw HTML embedded in a note.
+pub enum HtmlSanitizeMode {
+    /// Collect diagnostics about unbalanced or unclosed tags into the given `Vec`, without
+    /// modifying the note.
+    Report(Rc<RefCell<Vec<HtmlDiagnostic>>>),
+    /// Remove any tag not on `allowed_tags`, leaving the inner content intact.
+    Strip { allowed_tags: Vec<String> },
+}
+
+/// This postprocessor inspects raw HTML embedded in a note (`Event::Html` and
+/// `Event::InlineHtml`) and either reports unbalanced/unclosed tags or strips tags that aren't on
+/// an allow-list, for publishing targets that forbid (or require well-formed) raw HTML.
+pub fn sanitize_html(
+    mode: HtmlSanitizeMode,
+) -> impl Fn(&mut Context, &mut MarkdownEvents) -> PostprocessorResult {
+    let tag_re = Regex::new(r"<(/?)\s*([a-zA-Z][a-zA-Z0-9-]*)([^>]*?)(/?)>").unwrap();
+
+    move |_context: &mut Context, events: &mut MarkdownEvents| -> PostprocessorResult {
+        let mut open_tags: Vec<String> = Vec::new();
+
+        for event in events.iter_mut() {
+            match event {
+                Event::Html(ref text) => {
+                    if let Some(replacement) =
+                        sanitize_html_fragment(text, &tag_re, &mode, &mut open_tags)
+                    {
+                        *event = Event::Html(CowStr::from(replacement));
+                    }
+                }
+                Event::InlineHtml(ref text) => {
+                    if let Some(replacement) =
+                        sanitize_html_fragment(text, &tag_re, &mode, &mut open_tags)
+                    {
+                        *event = Event::InlineHtml(CowStr::from(replacement));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let HtmlSanitizeMode::Report(diagnostics) = &mode {
+            for tag in open_tags {
+                diagnostics.borrow_mut().push(HtmlDiagnostic {
+                    tag,
+                    context: "note ended with this tag still open".to_string(),
+                });
+            }
+        }
+
+        PostprocessorResult::Continue
+    }
+}
+
+/// Scans (or strips) the tags in a single HTML fragment, updating `open_tags` as it goes.
+/// Returns `Some(replacement)` in `Strip` mode, or `None` in `Report` mode (which records
+/// diagnostics instead of rewriting anything).
+fn sanitize_html_fragment(
+    text: &str,
+    tag_re: &Regex,
+    mode: &HtmlSanitizeMode,
+    open_tags: &mut Vec<String>,
+) -> Option<String> {
+    match mode {
+        HtmlSanitizeMode::Report(diagnostics) => {
+            for caps in tag_re.captures_iter(text) {
+                let closing = &caps[1] == "/";
+                let name = caps[2].to_lowercase();
+                let self_closing = &caps[4] == "/";
+
+                if VOID_ELEMENTS.contains(&name.as_str()) {
+                    continue;
+                }
+
+                if closing {
+                    match open_tags.last() {
+                        Some(top) if *top == name => {
+                            open_tags.pop();
+                        }
+                        _ => {
+                            diagnostics.borrow_mut().push(HtmlDiagnostic {
+                                tag: name,
+                                context: text.to_string(),
+                            });
+                        }
+                    }
+                } else if !self_closing {
+                    open_tags.push(name);
+                }
+            }
+            None
+        }
+        HtmlSanitizeMode::Strip { allowed_tags } => {
+            let stripped = tag_re.replace_all(text, |caps: &regex::Captures| {
+                let name = caps[2].to_lowercase();
+                if allowed_tags.iter().any(|allowed| allowed == &name) {
+                    caps[0].to_string()
+                } else {
+                    String::new()
+                }
+            });
+            Some(stripped.to_string())
+        }
+    }
+}
+
+impl Default for HtmlSanitizeMode {
+    fn default() -> Self {
+        HtmlSanitizeMode::Strip {
+            allowed_tags: DEFAULT_ALLOWED_TAGS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_sanitize_html_fragment_strip() {
+    let tag_re = Regex::new(r"<(/?)\s*([a-zA-Z][a-zA-Z0-9-]*)([^>]*?)(/?)>").unwrap();
+    let mode = HtmlSanitizeMode::default();
+    let mut open_tags = Vec::new();
+
+    let result =
+        sanitize_html_fragment("<script>alert(1)</script>", &tag_re, &mode, &mut open_tags);
+    assert_eq!(
+        result,
+        Some("alert(1)".to_string()),
+        "A tag that isn't on the allow-list is stripped, leaving its content intact"
+    );
+
+    let result = sanitize_html_fragment("<b>bold</b>", &tag_re, &mode, &mut open_tags);
+    assert_eq!(
+        result,
+        Some("<b>bold</b>".to_string()),
+        "A tag on the default allow-list is left untouched"
+    );
+}
+
+#[test]
+fn test_sanitize_html_fragment_report() {
+    let tag_re = Regex::new(r"<(/?)\s*([a-zA-Z][a-zA-Z0-9-]*)([^>]*?)(/?)>").unwrap();
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    let mode = HtmlSanitizeMode::Report(Rc::clone(&diagnostics));
+    let mut open_tags = Vec::new();
+
+    let result = sanitize_html_fragment("<b>bold</i>", &tag_re, &mode, &mut open_tags);
+    assert_eq!(result, None, "Report mode never rewrites the event");
+    assert_eq!(
+        *diagnostics.borrow(),
+        vec![HtmlDiagnostic {
+            tag: "i".to_string(),
+            context: "<b>bold</i>".to_string(),
+        }],
+        "A mismatched closing tag is recorded along with the HTML it was found in"
+    );
+    assert_eq!(
+        open_tags,
+        vec!["b".to_string()],
+        "The unmatched opening tag is still considered open"
+    );
+}
+
+#[test]
+fn test_sanitize_html_fragment_void_elements() {
+    let tag_re = Regex::new(r"<(/?)\s*([a-zA-Z][a-zA-Z0-9-]*)([^>]*?)(/?)>").unwrap();
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    let mode = HtmlSanitizeMode::Report(diagnostics);
+    let mut open_tags = Vec::new();
+
+    sanitize_html_fragment("line one<br>line two", &tag_re, &mode, &mut open_tags);
+    assert!(
+        open_tags.is_empty(),
+        "Void elements like <br> never need a closing tag"
+    );
+}
+
+/// This postprocessor rewrites ASCII punctuation into its typographic Unicode equivalent
+/// (straight quotes into curly quotes, `--`/`---` into en/em dashes, `...` into an ellipsis),
+/// mimicking Obsidian's _'Smart punctuation'_ preview setting. It's opt-in, since not every
+/// renderer wants this done ahead of time.
+pub fn smart_punctuation(
+    _context: &mut Context,
+    events: &mut MarkdownEvents,
+) -> PostprocessorResult {
+    let mut output = Vec::with_capacity(events.len());
+    let mut inside_codeblock = false;
+    let mut prev_char: Option<char> = None;
+
+    for event in &mut *events {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                inside_codeblock = true;
+                prev_char = None;
+                output.push(event.to_owned());
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                inside_codeblock = false;
+                prev_char = None;
+                output.push(event.to_owned());
+            }
+            Event::Code(ref text) => {
+                // A code span renders inline, right up against whatever follows it, so its last
+                // character (not `None`) is what decides whether a following quote is opening or
+                // closing.
+                if let Some(last) = text.chars().last() {
+                    prev_char = Some(last);
+                }
+                output.push(event.to_owned());
+            }
+            Event::Start(Tag::Emphasis)
+            | Event::Start(Tag::Strong)
+            | Event::Start(Tag::Strikethrough)
+            | Event::Start(Tag::Link(..))
+            | Event::Start(Tag::Image(..))
+            | Event::End(Tag::Emphasis)
+            | Event::End(Tag::Strong)
+            | Event::End(Tag::Strikethrough)
+            | Event::End(Tag::Link(..))
+            | Event::End(Tag::Image(..)) => {
+                // These wrap inline content without emitting a visible character of their own, so
+                // `prev_char` is left exactly as the inner text left it (e.g. `*word*'s` must see
+                // the `d` from "word", not `None`).
+                output.push(event.to_owned());
+            }
+            Event::Text(ref text) if !inside_codeblock => {
+                let (result, last_char) = typographic_replace(text, prev_char);
+                prev_char = last_char;
+                output.push(Event::Text(CowStr::from(result)));
+            }
+            _ => {
+                prev_char = None;
+                output.push(event.to_owned());
+            }
+        }
+    }
+
+    *events = output;
+    PostprocessorResult::Continue
+}
+
+/// Rewrites the ASCII punctuation in `text` into its typographic equivalent, given the last
+/// character seen before `text` (used to decide whether a quote opens or closes). Returns the
+/// rewritten text along with the last character of the original `text` (the ASCII character, not
+/// its typographic replacement), so callers can carry the heuristic across adjoining
+/// `Event::Text`s.
+fn typographic_replace(text: &str, mut prev_char: Option<char>) -> (String, Option<char>) {
+    const OPENING_CONTEXT: &str = "([{-–—";
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+
+        if c == '"' || c == '\'' {
+            let opening =
+                prev_char.map_or(true, |p| p.is_whitespace() || OPENING_CONTEXT.contains(p));
+            result.push(match (c, opening) {
+                ('"', true) => '\u{201C}',
+                ('"', false) => '\u{201D}',
+                (_, true) => '\u{2018}',
+                (_, false) => '\u{2019}',
+            });
+            prev_char = Some(c);
+        } else if rest.starts_with(&['-', '-', '-']) {
+            result.push('\u{2014}');
+            i += 2;
+            prev_char = Some('-');
+        } else if rest.starts_with(&['-', '-']) {
+            result.push('\u{2013}');
+            i += 1;
+            prev_char = Some('-');
+        } else if rest.starts_with(&['.', '.', '.']) {
+            result.push('\u{2026}');
+            i += 2;
+            prev_char = Some('.');
+        } else {
+            result.push(c);
+            prev_char = Some(c);
+        }
+
+        i += 1;
+    }
+
+    (result, prev_char)
+}
+
+#[test]
+fn test_typographic_replace() {
+    assert_eq!(
+        typographic_replace("\"hello\"", None),
+        ("\u{201C}hello\u{201D}".to_string(), Some('"')),
+        "A quoted word at the start of a text opens and closes"
+    );
+    assert_eq!(
+        typographic_replace("'s", Some('d')),
+        ("\u{2019}s".to_string(), Some('s')),
+        "An apostrophe right after a word character closes rather than opens"
+    );
+    assert_eq!(
+        typographic_replace("'tis", None),
+        ("\u{2018}tis".to_string(), Some('s')),
+        "A quote at the very start of a text (prev_char is None) opens"
+    );
+    assert_eq!(
+        typographic_replace("foo---bar", None),
+        ("foo\u{2014}bar".to_string(), Some('r')),
+        "Three hyphens become an em dash"
+    );
+    assert_eq!(
+        typographic_replace("foo--bar", None),
+        ("foo\u{2013}bar".to_string(), Some('r')),
+        "Two hyphens become an en dash"
+    );
+    assert_eq!(
+        typographic_replace("wait...", None),
+        ("wait\u{2026}".to_string(), Some('.')),
+        "Three dots become an ellipsis"
+    );
+}
+
+/// Configures which optional `pulldown-cmark` extensions are enabled when a note's Markdown is
+/// parsed into the event stream the postprocessors in this module operate on. Mirrors rustdoc's
+/// own `opts()`, except every extension is individually toggleable rather than always-on, since
+/// not every caller wants the richer events these extensions produce.
+#[derive(Debug, Clone, Copy)]
+pub struct ParserOptions {
+    pub tables: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub tasklists: bool,
+}
+
+impl Default for ParserOptions {
+    /// All extensions on by default, so footnotes, tables, strikethrough and task lists
+    /// round-trip instead of being parsed as plain text.
+    fn default() -> Self {
+        ParserOptions {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+        }
+    }
+}
+
+impl ParserOptions {
+    fn to_pulldown_options(self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, self.tasklists);
+        options
+    }
+}
+
+/// Parses `content` into the `pulldown-cmark` event stream the postprocessors in this module
+/// operate on, honoring `options`. The exporter's note-to-events call site (outside this module)
+/// must construct its `Parser` through here rather than `Parser::new`/`Parser::new_ext` directly,
+/// or `ParserOptions` never actually reaches the parser.
+pub fn parse(content: &str, options: ParserOptions) -> Parser<'_, '_> {
+    Parser::new_ext(content, options.to_pulldown_options())
+}
+
+#[test]
+fn test_parser_options() {
+    let default = ParserOptions::default().to_pulldown_options();
+    assert!(default.contains(Options::ENABLE_TABLES));
+    assert!(default.contains(Options::ENABLE_FOOTNOTES));
+    assert!(default.contains(Options::ENABLE_STRIKETHROUGH));
+    assert!(default.contains(Options::ENABLE_TASKLISTS));
+
+    let tables_only = ParserOptions {
+        tables: true,
+        footnotes: false,
+        strikethrough: false,
+        tasklists: false,
+    }
+    .to_pulldown_options();
+    assert!(tables_only.contains(Options::ENABLE_TABLES));
+    assert!(!tables_only.contains(Options::ENABLE_FOOTNOTES));
+    assert!(!tables_only.contains(Options::ENABLE_STRIKETHROUGH));
+    assert!(!tables_only.contains(Options::ENABLE_TASKLISTS));
+}
+
+#[test]
+fn test_parse_honors_options() {
+    let events: Vec<Event> = parse(
+        "- [ ] todo",
+        ParserOptions {
+            tasklists: true,
+            ..ParserOptions::default()
+        },
+    )
+    .collect();
+    assert!(
+        events.iter().any(|e| matches!(e, Event::TaskListMarker(_))),
+        "Enabling tasklists should produce a TaskListMarker event for `- [ ]`"
+    );
+
+    let events: Vec<Event> = parse(
+        "- [ ] todo",
+        ParserOptions {
+            tasklists: false,
+            ..ParserOptions::default()
+        },
+    )
+    .collect();
+    assert!(
+        !events.iter().any(|e| matches!(e, Event::TaskListMarker(_))),
+        "Disabling tasklists should parse the checkbox as plain text instead"
+    );
+}
+
+/// A reasonable default set of language aliases for [`normalize_code_block_languages`], mapping
+/// common shorthands and variant spellings onto the canonical tag most syntax highlighters
+/// expect.
+pub fn default_language_aliases() -> HashMap<String, String> {
+    [
+        ("js", "javascript"),
+        ("ts", "typescript"),
+        ("py", "python"),
+        ("rb", "ruby"),
+        ("sh", "bash"),
+        ("shell", "bash"),
+        ("yml", "yaml"),
+        ("c++", "cpp"),
+        ("hpp", "cpp"),
+        ("rs", "rust"),
+        ("md", "markdown"),
+    ]
+    .iter()
+    .map(|(from, to)| (from.to_string(), to.to_string()))
+    .collect()
+}
+
+/// A single diagnostic produced by [`normalize_code_block_languages`] when a fenced code block's
+/// language tag isn't recognized, so a caller (or a test) can see exactly which tag triggered it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageDiagnostic {
+    pub language: String,
+}
+
+/// This postprocessor normalizes fenced code block language tags through a configurable alias
+/// map, so that e.g. `js` and `javascript` end up with the same canonical tag, and can optionally
+/// collect diagnostics about tags it doesn't recognize into `diagnostics`, in the spirit of
+/// rustdoc's code-block syntax lint. Indented code blocks are left untouched, since they have no
+/// language tag to normalize. A tag counts as unrecognized only when it is neither a key of the
+/// alias map nor known to `syntect`'s bundled `SyntaxSet` (the same one [`highlight_code_blocks`]
+/// highlights with), since the alias map only lists shorthands and isn't meant to enumerate every
+/// language `syntect` already knows.
+pub fn normalize_code_block_languages(
+    aliases: HashMap<String, String>,
+    diagnostics: Option<Rc<RefCell<Vec<LanguageDiagnostic>>>>,
+) -> impl Fn(&mut Context, &mut MarkdownEvents) -> PostprocessorResult {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+
+    move |_context: &mut Context, events: &mut MarkdownEvents| -> PostprocessorResult {
+        let mut output = Vec::with_capacity(events.len());
+        let mut current_canonical: Option<CowStr> = None;
+
+        for event in &mut *events {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+                    let canonical = CowStr::from(normalize_language_tag(
+                        &*lang,
+                        &aliases,
+                        diagnostics.as_ref(),
+                        &syntax_set,
+                    ));
+                    current_canonical = Some(canonical.clone());
+                    output.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(
+                        canonical,
+                    ))));
+                }
+                Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(_))) => {
+                    let canonical = current_canonical.take().unwrap_or_else(|| CowStr::from(""));
+                    output.push(Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(canonical))));
+                }
+                _ => output.push(event.to_owned()),
+            }
+        }
+
+        *events = output;
+        PostprocessorResult::Continue
+    }
+}
+
+/// Normalizes a single fence info string (e.g. `js`, `rust,ignore`) against `aliases`, leaving
+/// any attributes after the language token untouched. Pushes a [`LanguageDiagnostic`] onto
+/// `diagnostics`, when given, if the language token isn't found in `aliases` or in `syntax_set`.
+fn normalize_language_tag(
+    lang: &str,
+    aliases: &HashMap<String, String>,
+    diagnostics: Option<&Rc<RefCell<Vec<LanguageDiagnostic>>>>,
+    syntax_set: &SyntaxSet,
+) -> String {
+    // The info string may carry trailing attributes after the language token (e.g.
+    // `rust,ignore` or `js live`), so only the first word is looked up. The separator is found
+    // by its char index rather than assumed to be a single byte, since CommonMark allows any
+    // Unicode whitespace there.
+    let trimmed = lang.trim();
+    let separator = trimmed
+        .char_indices()
+        .find(|&(_, c)| c == ',' || c.is_whitespace());
+    let (token, attributes) = match separator {
+        Some((idx, sep)) => (&trimmed[..idx], &trimmed[idx + sep.len_utf8()..]),
+        None => (trimmed, ""),
+    };
+    let key = token.to_lowercase();
+    let canonical_token = if key.is_empty() {
+        token.to_string()
+    } else if let Some(canonical) = aliases.get(&key) {
+        canonical.to_owned()
+    } else {
+        if let Some(diagnostics) = diagnostics {
+            if syntax_set.find_syntax_by_token(&key).is_none() {
+                diagnostics.borrow_mut().push(LanguageDiagnostic {
+                    language: token.to_string(),
+                });
+            }
+        }
+        key
+    };
+
+    match separator {
+        Some((_, sep)) => format!("{}{}{}", canonical_token, sep, attributes),
+        None => canonical_token,
+    }
+}
+
+#[test]
+fn test_normalize_language_tag() {
+    let aliases = default_language_aliases();
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+
+    assert_eq!(
+        normalize_language_tag("js", &aliases, None, &syntax_set),
+        "javascript",
+        "A known alias is rewritten to its canonical tag"
+    );
+    assert_eq!(
+        normalize_language_tag("rust,ignore", &aliases, None, &syntax_set),
+        "rust,ignore",
+        "Attributes after the language token are preserved untouched"
+    );
+    assert_eq!(
+        normalize_language_tag("rs,ignore", &aliases, None, &syntax_set),
+        "rust,ignore",
+        "An aliased language with attributes is normalized while keeping the attributes"
+    );
+    assert_eq!(
+        normalize_language_tag("rust\u{00A0}ignore", &aliases, None, &syntax_set),
+        "rust\u{00A0}ignore",
+        "A non-breaking-space separator (valid per CommonMark) doesn't panic"
+    );
+    assert_eq!(
+        normalize_language_tag("PYTHON", &aliases, None, &syntax_set),
+        "python",
+        "A language recognized by syntect but not in the alias map is still lowercased"
+    );
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    assert_eq!(
+        normalize_language_tag("", &aliases, Some(&diagnostics), &syntax_set),
+        "",
+        "An empty info string is left untouched and never produces a diagnostic"
+    );
+    assert!(
+        diagnostics.borrow().is_empty(),
+        "An empty info string is left untouched and never produces a diagnostic"
+    );
+}
+
+#[test]
+fn test_normalize_language_tag_warns_on_unknown() {
+    let aliases = default_language_aliases();
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+
+    let result = normalize_language_tag(
+        "nonexistentlang",
+        &aliases,
+        Some(&diagnostics),
+        &syntax_set,
+    );
+
+    assert_eq!(
+        result, "nonexistentlang",
+        "An unrecognized language is left as-is"
+    );
+    assert_eq!(
+        *diagnostics.borrow(),
+        vec![LanguageDiagnostic {
+            language: "nonexistentlang".to_string()
+        }],
+        "An unrecognized language produces exactly one diagnostic"
+    );
+}
+
 #[test]
 fn test_filter_tags() {
     let tags = vec![